@@ -0,0 +1,81 @@
+use anyhow::Error;
+use reqwest::header::HeaderMap;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::constants::Side;
+use crate::exchanges::exchange_types::{Balance, FetchPositionParams, Position, Ticker};
+use crate::exchanges::middleware::{HttpResponse, Middleware, Next, Request};
+
+/// Thin wrapper around the shared `reqwest::Client`, with a stack of
+/// `Middleware` layers (retry, rate-limiting, logging, ...) composed in front
+/// of the raw HTTP call. Exchange clients layer their own signing/parsing on
+/// top of the `HttpResponse` this returns.
+pub struct RestClient {
+    client: reqwest::Client,
+    middlewares: Vec<Arc<dyn Middleware>>,
+}
+
+impl RestClient {
+    pub fn new() -> Self {
+        RestClient {
+            client: reqwest::Client::new(),
+            middlewares: Vec::new(),
+        }
+    }
+
+    /// Appends a layer to the middleware stack. Layers added first run
+    /// outermost, matching the order they're passed to this builder.
+    pub fn with_middleware(mut self, middleware: Arc<dyn Middleware>) -> Self {
+        self.middlewares.push(middleware);
+        self
+    }
+
+    pub async fn send_request(
+        &self,
+        method: &str,
+        url: &str,
+        headers: Option<HeaderMap>,
+        body: Option<HashMap<String, String>>,
+    ) -> Result<HttpResponse, Error> {
+        let req = Request {
+            method: method.to_string(),
+            url: url.to_string(),
+            headers,
+            body,
+        };
+        Next::new(&self.middlewares, self).run(req).await
+    }
+
+    /// The terminal call at the bottom of the middleware stack: actually
+    /// issues the HTTP request and reads the body eagerly.
+    pub(crate) async fn send_raw(&self, req: Request) -> Result<HttpResponse, Error> {
+        let mut request = self.client.request(req.method.parse()?, &req.url);
+        if let Some(headers) = req.headers {
+            request = request.headers(headers);
+        }
+        if req.method == "POST" {
+            if let Some(body) = &req.body {
+                request = request.json(body);
+            }
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        let body = response.text().await?;
+        Ok(HttpResponse { status, body })
+    }
+}
+
+/// Common surface implemented by every exchange client so strategies can be
+/// written against `dyn BaseExchange` instead of a concrete exchange type.
+#[allow(async_fn_in_trait)]
+pub trait BaseExchange {
+    async fn get_ticker(&self, symbol: &str) -> Result<Ticker, Error>;
+
+    async fn fetch_positions(&self, params: FetchPositionParams) -> Result<Vec<Position>, Error>;
+
+    async fn fetch_balances(&self) -> Result<Vec<Balance>, Error>;
+
+    async fn get_bbo_price(&self, symbol: &str, side: Side) -> Result<f64, Error>;
+}
@@ -0,0 +1,53 @@
+use serde::Deserialize;
+
+/// Filters accepted by `BaseExchange::fetch_positions`.
+pub struct FetchPositionParams {
+    pub symbol: Option<String>,
+}
+
+/// Decoded form of a single entry in OKX's `/api/v5/market/ticker` `data` array.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Ticker {
+    #[serde(rename = "instId")]
+    pub inst_id: String,
+    #[serde(rename = "last")]
+    pub last_price: String,
+    #[serde(rename = "bidPx")]
+    pub bid_price: String,
+    #[serde(rename = "askPx")]
+    pub ask_price: String,
+    #[serde(rename = "ts")]
+    pub timestamp: String,
+}
+
+/// Decoded form of a single entry in OKX's `/api/v5/account/balance` `data`
+/// array. The per-currency balances this wraps live in `details`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AccountBalance {
+    pub details: Vec<Balance>,
+}
+
+/// Decoded form of a single entry in OKX's `/api/v5/account/balance`
+/// `data[].details` array.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Balance {
+    #[serde(rename = "ccy")]
+    pub currency: String,
+    #[serde(rename = "availBal")]
+    pub available_balance: String,
+    #[serde(rename = "cashBal")]
+    pub cash_balance: String,
+}
+
+/// Decoded form of a single entry in OKX's `/api/v5/account/positions` `data` array.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Position {
+    #[serde(rename = "instId")]
+    pub inst_id: String,
+    #[serde(rename = "pos")]
+    pub position: String,
+    #[serde(rename = "avgPx")]
+    pub avg_price: String,
+    #[serde(rename = "upl")]
+    pub unrealized_pnl: String,
+}
@@ -3,16 +3,39 @@ use base64::{engine::general_purpose::STANDARD, Engine as _};
 use chrono::Utc;
 use hmac::{Hmac, Mac};
 use reqwest::header::HeaderMap;
-use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
 use serde_json::{to_string, Value};
 use sha2::Sha256;
 use std::collections::HashMap;
 use std::string::String;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 use crate::constants::{Side};
 use url::form_urlencoded;
 
 use crate::exchanges::base::{BaseExchange, RestClient};
-use crate::exchanges::exchange_types::FetchPositionParams;
+use crate::exchanges::exchange_types::{AccountBalance, Balance, FetchPositionParams, Position, Ticker};
+use crate::exchanges::middleware::{HttpResponse, LoggingMiddleware, RateLimitMiddleware, RetryMiddleware};
+use crate::exchanges::okx_error::OkxError;
+
+/// Per-endpoint request weights for `RateLimitMiddleware`: the number of
+/// tokens each call to that path spends from its own token bucket. Not
+/// exhaustive: endpoints missing here spend 1 token per call.
+fn endpoint_weights() -> HashMap<String, f64> {
+    HashMap::from([
+        ("/api/v5/account/balance".to_string(), 5.0),
+        ("/api/v5/account/positions".to_string(), 5.0),
+        ("/api/v5/market/ticker".to_string(), 1.0),
+        ("/api/v5/market/books".to_string(), 1.0),
+        ("/api/v5/public/time".to_string(), 1.0),
+    ])
+}
+
+/// OKX rejects requests whose `OK-ACCESS-TIMESTAMP` drifts more than ~30s from
+/// server time. Mirrors the role Binance's `recv_window` plays there.
+const DEFAULT_RECV_WINDOW_MS: i64 = 30_000;
 
 pub struct OkxExchange {
     key: String,
@@ -21,6 +44,15 @@ pub struct OkxExchange {
     base_url: String,
     is_demo: bool,
     rest_client: RestClient,
+    /// Advisory only: OKX has no client-sent recv-window header, so this
+    /// doesn't change what gets sent on the wire. It's the interval callers
+    /// should judge `sync_clock_offset` against (e.g. "resync at most this
+    /// often before request timestamps might start drifting outside it").
+    recv_window_ms: i64,
+    /// `server_time - local_time`, in ms, as of the last `sync_clock_offset` call.
+    /// Added into every signed timestamp so long-running clients with skewed
+    /// clocks don't start failing OKX's expiry check.
+    clock_offset_ms: AtomicI64,
 }
 
 impl OkxExchange {
@@ -30,7 +62,18 @@ impl OkxExchange {
         let passphrase:String = configs.get("passphrase").unwrap().to_string();
         let base_url: String = "https://www.okx.com".to_string();
         let is_demo: bool = configs.get("is_demo").and_then(|s| s.parse::<bool>().ok()).unwrap_or(false);
-        let rest_client: RestClient = RestClient::new();
+        let rest_client: RestClient = RestClient::new()
+            .with_middleware(Arc::new(LoggingMiddleware))
+            .with_middleware(Arc::new(RetryMiddleware::new(3, Duration::from_millis(250))))
+            .with_middleware(Arc::new(RateLimitMiddleware::new(5.0, 2.5, endpoint_weights())));
+        let recv_window_ms: i64 = configs
+            .get("recv_window_ms")
+            .and_then(|s| s.parse::<i64>().ok())
+            .unwrap_or(DEFAULT_RECV_WINDOW_MS);
+        let clock_offset_ms: i64 = configs
+            .get("clock_offset_ms")
+            .and_then(|s| s.parse::<i64>().ok())
+            .unwrap_or(0);
 
         OkxExchange {
             key,
@@ -39,9 +82,37 @@ impl OkxExchange {
             base_url,
             is_demo,
             rest_client,
+            recv_window_ms,
+            clock_offset_ms: AtomicI64::new(clock_offset_ms),
         }
     }
 
+    /// Advisory only — see the field doc comment on `OkxExchange`. Does not
+    /// change what's sent on the wire.
+    pub fn recv_window_ms(&self) -> i64 {
+        self.recv_window_ms
+    }
+
+    pub fn clock_offset_ms(&self) -> i64 {
+        self.clock_offset_ms.load(Ordering::Relaxed)
+    }
+
+    /// Refreshes `clock_offset_ms` against OKX's public clock. Callers with
+    /// long-lived clients should invoke this on an interval well inside
+    /// `recv_window_ms` (e.g. every few minutes) to stay ahead of clock drift.
+    pub async fn sync_clock_offset(&self) -> Result<(), Error> {
+        let times: Vec<ServerTime> = self
+            .send_public_request("GET", "/api/v5/public/time")
+            .await?;
+        let server_time = times
+            .first()
+            .ok_or_else(|| anyhow!("no server time returned"))?;
+        let server_ms: i64 = server_time.ts.parse()?;
+        let local_ms = Utc::now().timestamp_millis();
+        self.clock_offset_ms.store(server_ms - local_ms, Ordering::Relaxed);
+        Ok(())
+    }
+
     // Signature definition is specific to the exchange
     fn generate_signature(
         &self,
@@ -50,7 +121,9 @@ impl OkxExchange {
         query_string: &str,
         body: &str,
     ) -> (String, String) {
-        let timestamp = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        let offset_ms = self.clock_offset_ms.load(Ordering::Relaxed);
+        let timestamp = (Utc::now() + chrono::Duration::milliseconds(offset_ms))
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
         let pre_hash = format!("{}{}{}{}{}", timestamp, method, url, query_string, body);
         let mut mac = Hmac::<Sha256>::new_from_slice(self.secret.as_bytes()).unwrap();
         mac.update(pre_hash.as_bytes());
@@ -74,12 +147,12 @@ impl OkxExchange {
         headers
     }
 
-    async fn send_request(
+    async fn send_request<T: DeserializeOwned>(
         &self,
         method: &str,
         endpoint: &str,
         body: Option<HashMap<String, String>>,
-    ) -> Result<HashMap<String, String>, Error> {
+    ) -> Result<T, Error> {
         let query_string = match &body {
             Some(map) => {
                 let query = form_urlencoded::Serializer::new(String::new())
@@ -108,53 +181,258 @@ impl OkxExchange {
             .send_request(method, &url, Some(headers), body)
             .await?;
 
-        let status: StatusCode = response.status();
-        let text = response.text().await?;
-        if status.is_success() {
-            let result: HashMap<String, String> = serde_json::from_str(&text)?;
-            Ok(result)
-        } else {
-            Err(anyhow!(
+        Self::parse_response(method, response)
+    }
+
+    /// Unauthenticated counterpart of `send_request` for OKX's public market
+    /// data endpoints: skips `generate_signature`/`get_headers` entirely
+    /// rather than attaching `OK-ACCESS-*` headers nobody needs.
+    async fn send_public_request<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        endpoint: &str,
+    ) -> Result<T, Error> {
+        let url = format!("{}{}", self.base_url, endpoint);
+        let response = self.rest_client.send_request(method, &url, None, None).await?;
+        Self::parse_response(method, response)
+    }
+
+    /// Shared status/envelope/business-error handling for both the signed
+    /// and public request paths.
+    fn parse_response<T: DeserializeOwned>(method: &str, response: HttpResponse) -> Result<T, Error> {
+        if !response.status.is_success() {
+            return Err(anyhow!(
                 "{} request failed with status: {} and body: {}",
                 method,
-                status,
-                text
-            ))
+                response.status,
+                response.body
+            ));
         }
+
+        let envelope: RawEnvelope = serde_json::from_str(&response.body)?;
+        if envelope.code != "0" {
+            return Err(OkxError::RequestRejected {
+                code: envelope.code,
+                message: envelope.msg,
+            }
+            .into());
+        }
+        if let Value::Array(items) = &envelope.data {
+            for item in items {
+                let s_code = item.get("sCode").and_then(Value::as_str);
+                if let Some(s_code) = s_code {
+                    if s_code != "0" {
+                        let s_msg = item
+                            .get("sMsg")
+                            .and_then(Value::as_str)
+                            .unwrap_or_default()
+                            .to_string();
+                        return Err(OkxError::ItemRejected {
+                            code: s_code.to_string(),
+                            message: s_msg,
+                        }
+                        .into());
+                    }
+                }
+            }
+        }
+
+        let result: T = serde_json::from_value(envelope.data)?;
+        Ok(result)
     }
 }
 
+/// Envelope header OKX wraps every REST response in. Parsed before the
+/// `data` payload itself so business error codes (`code`/`msg`, and the
+/// per-item `sCode`/`sMsg` batched endpoints use) can be checked first.
+#[derive(serde::Deserialize)]
+struct RawEnvelope {
+    code: String,
+    msg: String,
+    data: Value,
+}
+
+/// Response shape of `/api/v5/public/time`.
+#[derive(Debug, Deserialize)]
+struct ServerTime {
+    ts: String,
+}
+
+/// Response shape of `/api/v5/market/books`. Each level is `[price, size,
+/// deprecated, numOrders]` per OKX's docs; only the price is used here.
+#[derive(Debug, Deserialize)]
+struct OrderBook {
+    asks: Vec<(String, String, String, String)>,
+    bids: Vec<(String, String, String, String)>,
+}
+
+fn flatten_balances(accounts: Vec<AccountBalance>) -> Vec<Balance> {
+    accounts.into_iter().flat_map(|account| account.details).collect()
+}
+
+/// Selling fills against the best bid; buying fills against the best ask.
+fn select_bbo_price(book: &OrderBook, side: Side, symbol: &str) -> Result<f64, Error> {
+    let levels = match side {
+        Side::Sell => &book.bids,
+        Side::Buy => &book.asks,
+    };
+    let (price, ..) = levels
+        .first()
+        .ok_or_else(|| anyhow!("order book for {} has no levels on that side", symbol))?;
+
+    Ok(price.parse::<f64>()?)
+}
+
 impl BaseExchange for OkxExchange {
-    async fn get_ticker(&self, symbol: &str) -> Result<HashMap<String, String>, Error> {
-        todo!()
+    async fn get_ticker(&self, symbol: &str) -> Result<Ticker, Error> {
+        let endpoint = format!("/api/v5/market/ticker?instId={}", symbol);
+        let tickers: Vec<Ticker> = self.send_public_request("GET", &endpoint).await?;
+        tickers
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("no ticker data returned for {}", symbol))
     }
 
     async fn fetch_positions(
         &self,
-        params: FetchPositionParams,
-    ) -> Result<Vec<HashMap<String, String>>, Error> {
-        todo!()
+        _params: FetchPositionParams,
+    ) -> Result<Vec<Position>, Error> {
+        Err(anyhow!("OkxExchange::fetch_positions is not implemented"))
     }
 
-    async fn fetch_balances(&self) -> Result<Vec<HashMap<String, String>>, Error> {
+    async fn fetch_balances(&self) -> Result<Vec<Balance>, Error> {
         let endpoint: &str = "/api/v5/account/balance";
-        let response = self.send_request("GET", endpoint, None).await?;
+        let accounts: Vec<AccountBalance> = self.send_request("GET", endpoint, None).await?;
+        Ok(flatten_balances(accounts))
+    }
 
-        let response_value: Value = serde_json::to_value(response)?;
+    async fn get_bbo_price(&self, symbol: &str, side: Side) -> Result<f64, Error> {
+        let endpoint = format!("/api/v5/market/books?instId={}&sz=1", symbol);
+        let books: Vec<OrderBook> = self.send_public_request("GET", &endpoint).await?;
+        let book = books
+            .first()
+            .ok_or_else(|| anyhow!("no order book data returned for {}", symbol))?;
 
-        if let Some(data) = response_value.get("data") {
-            if data.is_array() {
-                let balances: Vec<HashMap<String, String>> = serde_json::from_value(data.clone())?;
-                Ok(balances)
-            } else {
-                Err(anyhow!("Data is not an array."))
-            }
-        } else {
-            Err(anyhow!("No balance data found in the response."))
+        select_bbo_price(book, side, symbol)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::StatusCode;
+
+    fn http_response(status: StatusCode, body: &str) -> HttpResponse {
+        HttpResponse {
+            status,
+            body: body.to_string(),
         }
     }
 
-    async fn get_bbo_price(&self, symbol: &str, side: Side) -> Result<f64, Error> {
-        todo!()
+    #[test]
+    fn parse_response_decodes_successful_envelope() {
+        let response = http_response(
+            StatusCode::OK,
+            r#"{"code":"0","msg":"","data":[{"instId":"BTC-USDT","last":"42000","bidPx":"41999","askPx":"42001","ts":"123"}]}"#,
+        );
+
+        let tickers: Vec<Ticker> = OkxExchange::parse_response("GET", response).unwrap();
+
+        assert_eq!(tickers.len(), 1);
+        assert_eq!(tickers[0].inst_id, "BTC-USDT");
+        assert_eq!(tickers[0].last_price, "42000");
+    }
+
+    #[test]
+    fn parse_response_rejects_non_zero_top_level_code() {
+        let response = http_response(
+            StatusCode::OK,
+            r#"{"code":"50102","msg":"Timestamp request expired","data":[]}"#,
+        );
+
+        let err = OkxExchange::parse_response::<Vec<Ticker>>("GET", response).unwrap_err();
+
+        let okx_err = err.downcast_ref::<OkxError>().expect("expected an OkxError");
+        assert_eq!(okx_err.code(), "50102");
+        assert!(matches!(okx_err, OkxError::RequestRejected { .. }));
+    }
+
+    #[test]
+    fn parse_response_rejects_non_zero_item_scode() {
+        let response = http_response(
+            StatusCode::OK,
+            r#"{"code":"0","msg":"","data":[{"sCode":"51008","sMsg":"insufficient balance","ordId":"1"}]}"#,
+        );
+
+        let err = OkxExchange::parse_response::<Value>("GET", response).unwrap_err();
+
+        let okx_err = err.downcast_ref::<OkxError>().expect("expected an OkxError");
+        assert_eq!(okx_err.code(), "51008");
+        assert!(matches!(okx_err, OkxError::ItemRejected { .. }));
+    }
+
+    #[test]
+    fn parse_response_surfaces_http_errors() {
+        let response = http_response(StatusCode::INTERNAL_SERVER_ERROR, "boom");
+
+        let err = OkxExchange::parse_response::<Value>("GET", response).unwrap_err();
+
+        assert!(err.to_string().contains("500"));
+    }
+
+    #[test]
+    fn flatten_balances_pulls_details_out_of_each_account() {
+        let accounts = vec![
+            AccountBalance {
+                details: vec![Balance {
+                    currency: "USDT".to_string(),
+                    available_balance: "100".to_string(),
+                    cash_balance: "100".to_string(),
+                }],
+            },
+            AccountBalance {
+                details: vec![Balance {
+                    currency: "BTC".to_string(),
+                    available_balance: "1".to_string(),
+                    cash_balance: "1".to_string(),
+                }],
+            },
+        ];
+
+        let balances = flatten_balances(accounts);
+
+        assert_eq!(balances.len(), 2);
+        assert_eq!(balances[0].currency, "USDT");
+        assert_eq!(balances[1].currency, "BTC");
+    }
+
+    fn sample_book() -> OrderBook {
+        OrderBook {
+            asks: vec![("101".to_string(), "1".to_string(), "0".to_string(), "1".to_string())],
+            bids: vec![("99".to_string(), "1".to_string(), "0".to_string(), "1".to_string())],
+        }
+    }
+
+    #[test]
+    fn select_bbo_price_uses_best_bid_for_sell_side() {
+        let price = select_bbo_price(&sample_book(), Side::Sell, "BTC-USDT").unwrap();
+        assert_eq!(price, 99.0);
+    }
+
+    #[test]
+    fn select_bbo_price_uses_best_ask_for_buy_side() {
+        let price = select_bbo_price(&sample_book(), Side::Buy, "BTC-USDT").unwrap();
+        assert_eq!(price, 101.0);
+    }
+
+    #[test]
+    fn select_bbo_price_errors_when_side_has_no_levels() {
+        let book = OrderBook {
+            asks: vec![],
+            bids: vec![],
+        };
+
+        let err = select_bbo_price(&book, Side::Sell, "BTC-USDT").unwrap_err();
+        assert!(err.to_string().contains("BTC-USDT"));
     }
 }
@@ -0,0 +1,64 @@
+use std::fmt;
+
+/// A non-zero `code`/`sCode` OKX returned for a request or for one item of a
+/// batched request. The raw code/message are always preserved so callers can
+/// match on them even when `error_messages` doesn't know the code yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OkxError {
+    /// Top-level envelope `code` was non-zero: the whole request was rejected.
+    RequestRejected { code: String, message: String },
+    /// The envelope `code` was `"0"` but one item of a batched `data` array
+    /// carried a non-zero `sCode` (e.g. one order in a multi-order submit).
+    ItemRejected { code: String, message: String },
+}
+
+impl OkxError {
+    pub fn code(&self) -> &str {
+        match self {
+            OkxError::RequestRejected { code, .. } => code,
+            OkxError::ItemRejected { code, .. } => code,
+        }
+    }
+
+    /// Looks up a friendlier description for well-known codes, falling back
+    /// to the message OKX sent.
+    pub fn description(&self) -> String {
+        let (code, msg) = match self {
+            OkxError::RequestRejected { code, message } => (code, message),
+            OkxError::ItemRejected { code, message } => (code, message),
+        };
+        match error_messages(code) {
+            Some(known) => format!("{known} ({msg})"),
+            None => msg.clone(),
+        }
+    }
+}
+
+impl fmt::Display for OkxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OkxError::RequestRejected { code, .. } => {
+                write!(f, "OKX request rejected [{code}]: {}", self.description())
+            }
+            OkxError::ItemRejected { code, .. } => {
+                write!(f, "OKX item rejected [{code}]: {}", self.description())
+            }
+        }
+    }
+}
+
+impl std::error::Error for OkxError {}
+
+/// Well-known OKX `code`/`sCode` values worth surfacing with a clearer
+/// description than the raw `msg`. Not exhaustive; unknown codes fall back
+/// to OKX's own message.
+fn error_messages(code: &str) -> Option<&'static str> {
+    match code {
+        "50011" => Some("Rate limit reached"),
+        "50013" => Some("Service temporarily unavailable"),
+        "50102" => Some("Timestamp request expired"),
+        "51008" => Some("Order placement failed: insufficient balance"),
+        "58101" => Some("Account blocked"),
+        _ => None,
+    }
+}
@@ -0,0 +1,286 @@
+use anyhow::{anyhow, Error};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chrono::Utc;
+use futures_util::{SinkExt, StreamExt};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, Mutex};
+use tokio::time::interval;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::exchanges::exchange_types::{Position, Ticker};
+
+pub const OKX_PUBLIC_WS_URL: &str = "wss://ws.okx.com:8443/ws/v5/public";
+pub const OKX_PRIVATE_WS_URL: &str = "wss://ws.okx.com:8443/ws/v5/private";
+pub const OKX_DEMO_PUBLIC_WS_URL: &str = "wss://wspap.okx.com:8443/ws/v5/public";
+pub const OKX_DEMO_PRIVATE_WS_URL: &str = "wss://wspap.okx.com:8443/ws/v5/private";
+
+const PING_INTERVAL: Duration = Duration::from_secs(20);
+const RECONNECT_BACKOFF: Duration = Duration::from_secs(2);
+const UPDATES_CHANNEL_CAPACITY: usize = 1024;
+const COMMANDS_CHANNEL_CAPACITY: usize = 256;
+
+/// A subscribable OKX websocket channel. `tickers`/`books` are public;
+/// `positions` requires the login frame.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Channel {
+    Tickers { inst_id: String },
+    Books { inst_id: String },
+    Positions,
+}
+
+impl Channel {
+    fn is_private(&self) -> bool {
+        matches!(self, Channel::Positions)
+    }
+
+    /// The `{"channel": ..., "instId": ...}` arg OKX expects in
+    /// subscribe/unsubscribe messages.
+    fn subscribe_arg(&self) -> Value {
+        match self {
+            Channel::Tickers { inst_id } => json!({"channel": "tickers", "instId": inst_id}),
+            Channel::Books { inst_id } => json!({"channel": "books", "instId": inst_id}),
+            Channel::Positions => json!({"channel": "positions", "instType": "ANY"}),
+        }
+    }
+}
+
+/// A subscribe/unsubscribe request issued after the connection loops are
+/// already running. Broadcast so both the public and private loop can see
+/// it and each act only on the channels meant for it.
+#[derive(Debug, Clone)]
+enum Command {
+    Subscribe(Channel),
+    Unsubscribe(Channel),
+}
+
+/// Decoded form of a `books` channel push: OKX's top-of-book/incremental
+/// update shape, same 4-tuple levels as the REST order book endpoint.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BookUpdate {
+    #[serde(rename = "instId")]
+    pub inst_id: String,
+    pub asks: Vec<(String, String, String, String)>,
+    pub bids: Vec<(String, String, String, String)>,
+    pub ts: String,
+}
+
+/// A decoded push from any subscribed channel, as delivered to consumers via
+/// `WsClient::updates()`.
+#[derive(Debug, Clone)]
+pub enum MarketUpdate {
+    Ticker(Ticker),
+    Book(BookUpdate),
+    Position(Position),
+}
+
+/// Push-based counterpart to `BaseExchange`'s polling REST calls. Connects to
+/// OKX's public and private websocket endpoints, resubscribes on reconnect,
+/// and republishes decoded pushes on a broadcast channel so multiple
+/// strategies can consume the same feed.
+pub struct WsClient {
+    key: String,
+    secret: String,
+    passphrase: String,
+    is_demo: bool,
+    updates_tx: broadcast::Sender<MarketUpdate>,
+    subscriptions: Arc<Mutex<HashSet<Channel>>>,
+    /// Delivers `subscribe`/`unsubscribe` calls made after `start()` to
+    /// whichever connection loop is live, in addition to the snapshot each
+    /// loop replays from `subscriptions` on (re)connect.
+    commands_tx: broadcast::Sender<Command>,
+}
+
+impl WsClient {
+    pub fn new(configs: &HashMap<String, String>) -> Self {
+        let key = configs.get("key").cloned().unwrap_or_default();
+        let secret = configs.get("secret").cloned().unwrap_or_default();
+        let passphrase = configs.get("passphrase").cloned().unwrap_or_default();
+        let is_demo = configs
+            .get("is_demo")
+            .and_then(|s| s.parse::<bool>().ok())
+            .unwrap_or(false);
+        let (updates_tx, _) = broadcast::channel(UPDATES_CHANNEL_CAPACITY);
+        let (commands_tx, _) = broadcast::channel(COMMANDS_CHANNEL_CAPACITY);
+
+        WsClient {
+            key,
+            secret,
+            passphrase,
+            is_demo,
+            updates_tx,
+            subscriptions: Arc::new(Mutex::new(HashSet::new())),
+            commands_tx,
+        }
+    }
+
+    /// A fresh subscriber to every update published from here on. Channels
+    /// must still be requested via `subscribe`.
+    pub fn updates(&self) -> broadcast::Receiver<MarketUpdate> {
+        self.updates_tx.subscribe()
+    }
+
+    /// Spawns the public and private connection loops. Each reconnects with
+    /// backoff and resubscribes to whatever `subscribe` has accumulated.
+    pub fn start(self: &Arc<Self>) {
+        let public_url = if self.is_demo { OKX_DEMO_PUBLIC_WS_URL } else { OKX_PUBLIC_WS_URL };
+        let private_url = if self.is_demo { OKX_DEMO_PRIVATE_WS_URL } else { OKX_PRIVATE_WS_URL };
+
+        let public = Arc::clone(self);
+        let public_commands = self.commands_tx.subscribe();
+        tokio::spawn(async move { public.run_forever(public_url, false, public_commands).await });
+
+        let private = Arc::clone(self);
+        let private_commands = self.commands_tx.subscribe();
+        tokio::spawn(async move { private.run_forever(private_url, true, private_commands).await });
+    }
+
+    /// Marks a channel as wanted and, if a matching connection is already
+    /// live, sends the subscribe frame immediately. Either way, reconnects
+    /// replay every tracked channel from `subscriptions`.
+    pub async fn subscribe(&self, channel: Channel) -> Result<(), Error> {
+        self.subscriptions.lock().await.insert(channel.clone());
+        // No receiver (e.g. before `start()`) just means the initial
+        // connect will pick it up from `subscriptions` instead.
+        let _ = self.commands_tx.send(Command::Subscribe(channel));
+        Ok(())
+    }
+
+    /// Removes a channel and, if a matching connection is live, sends the
+    /// unsubscribe frame immediately.
+    pub async fn unsubscribe(&self, channel: &Channel) -> Result<(), Error> {
+        self.subscriptions.lock().await.remove(channel);
+        let _ = self.commands_tx.send(Command::Unsubscribe(channel.clone()));
+        Ok(())
+    }
+
+    async fn run_forever(self: Arc<Self>, url: &str, private: bool, mut commands: broadcast::Receiver<Command>) {
+        loop {
+            if let Err(err) = self.connect_and_serve(url, private, &mut commands).await {
+                log::warn!("okx ws ({url}) disconnected: {err}, reconnecting");
+            }
+            tokio::time::sleep(RECONNECT_BACKOFF).await;
+        }
+    }
+
+    async fn connect_and_serve(
+        &self,
+        url: &str,
+        private: bool,
+        commands: &mut broadcast::Receiver<Command>,
+    ) -> Result<(), Error> {
+        let (ws_stream, _) = tokio_tungstenite::connect_async(url).await?;
+        let (mut write, mut read) = ws_stream.split();
+
+        if private {
+            let (timestamp, signature) = self.login_signature();
+            let login = json!({
+                "op": "login",
+                "args": [{
+                    "apiKey": self.key,
+                    "passphrase": self.passphrase,
+                    "timestamp": timestamp,
+                    "sign": signature,
+                }],
+            });
+            write.send(Message::Text(login.to_string())).await?;
+        }
+
+        let wanted: Vec<Channel> = self
+            .subscriptions
+            .lock()
+            .await
+            .iter()
+            .filter(|c| c.is_private() == private)
+            .cloned()
+            .collect();
+        if !wanted.is_empty() {
+            let args: Vec<Value> = wanted.iter().map(Channel::subscribe_arg).collect();
+            let subscribe = json!({"op": "subscribe", "args": args});
+            write.send(Message::Text(subscribe.to_string())).await?;
+        }
+
+        let mut keepalive = interval(PING_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = keepalive.tick() => {
+                    write.send(Message::Text("ping".to_string())).await?;
+                }
+                message = read.next() => {
+                    match message {
+                        Some(Ok(Message::Text(text))) => self.handle_text(&text),
+                        Some(Ok(Message::Ping(payload))) => write.send(Message::Pong(payload)).await?,
+                        Some(Ok(Message::Close(_))) | None => return Err(anyhow!("websocket closed")),
+                        Some(Ok(_)) => {}
+                        Some(Err(err)) => return Err(err.into()),
+                    }
+                }
+                command = commands.recv() => {
+                    match command {
+                        Ok(Command::Subscribe(channel)) if channel.is_private() == private => {
+                            let op = json!({"op": "subscribe", "args": [channel.subscribe_arg()]});
+                            write.send(Message::Text(op.to_string())).await?;
+                        }
+                        Ok(Command::Unsubscribe(channel)) if channel.is_private() == private => {
+                            let op = json!({"op": "unsubscribe", "args": [channel.subscribe_arg()]});
+                            write.send(Message::Text(op.to_string())).await?;
+                        }
+                        // Meant for the other (public/private) connection.
+                        Ok(_) => {}
+                        // Missed some commands; the next reconnect resyncs
+                        // fully from `subscriptions` so this is non-fatal.
+                        Err(broadcast::error::RecvError::Lagged(_)) => {}
+                        Err(broadcast::error::RecvError::Closed) => {
+                            return Err(anyhow!("command channel closed"));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn handle_text(&self, text: &str) {
+        if text == "pong" {
+            return;
+        }
+        let Ok(frame) = serde_json::from_str::<Value>(text) else {
+            return;
+        };
+        let Some(channel) = frame.pointer("/arg/channel").and_then(Value::as_str) else {
+            return;
+        };
+        let Some(data) = frame.get("data").and_then(Value::as_array) else {
+            return;
+        };
+
+        for item in data {
+            let update = match channel {
+                "tickers" => serde_json::from_value(item.clone()).ok().map(MarketUpdate::Ticker),
+                "books" => serde_json::from_value(item.clone()).ok().map(MarketUpdate::Book),
+                "positions" => serde_json::from_value(item.clone()).ok().map(MarketUpdate::Position),
+                _ => None,
+            };
+            if let Some(update) = update {
+                // No subscribers is the common case when nobody has called
+                // `updates()` yet; not an error.
+                let _ = self.updates_tx.send(update);
+            }
+        }
+    }
+
+    /// Same HMAC-SHA256 scheme `OkxExchange::generate_signature` uses, over
+    /// the fixed `timestamp+"GET"+"/users/self/verify"` login challenge.
+    fn login_signature(&self) -> (String, String) {
+        let timestamp = Utc::now().timestamp().to_string();
+        let pre_hash = format!("{}GET/users/self/verify", timestamp);
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.secret.as_bytes()).unwrap();
+        mac.update(pre_hash.as_bytes());
+        let signature = STANDARD.encode(mac.finalize().into_bytes());
+        (timestamp, signature)
+    }
+}